@@ -7,7 +7,9 @@
 //! - `mp3_rust_session_run_impl`
 //! - `mp3_rust_session_deinit_impl`
 //!
-//! **Текущая реализация**: заглушки, возвращающие фиксированные значения.
+//! Разбор ведётся потоково через `read_at`: сканер синхронизируется по
+//! заголовку MPEG-кадра, декодирует версию/слой/битрейт и вычисляет
+//! длительность по цепочке кадров.
 
 use core::ffi::c_void;
 
@@ -41,8 +43,26 @@ pub struct Mp3AudioInfo {
     pub duration_ms: u32,
     pub data_size: u64,
     pub valid: u8,
+    /// Как получена `duration_ms`: см. `MP3_DURATION_*`.
+    pub duration_method: u8,
+    /// Задержка энкодера (сэмплы) из LAME-тега; `0`, если тега нет.
+    pub encoder_delay: u16,
+    /// Хвостовой паддинг энкодера (сэмплы) из LAME-тега.
+    pub encoder_padding: u16,
+    /// ReplayGain дорожки в единицах 0.1 дБ (знаковое число, уже
+    /// декодированное из знак-величины); `0`, если нет.
+    pub replaygain_track: i16,
+    /// ReplayGain альбома в единицах 0.1 дБ (знаковое число).
+    pub replaygain_album: i16,
 }
 
+// Способы вычисления длительности (поле `Mp3AudioInfo.duration_method`).
+/// Оценка по размеру и битрейту (CBR-приближение).
+const MP3_DURATION_ESTIMATED: u8 = 0;
+/// Точный подсчёт: кадры MPEG (обход цепочки либо Xing/Info/VBRI) или длина
+/// чанка `data` для WAVE.
+const MP3_DURATION_MEASURED: u8 = 1;
+
 /// Тип callback чтения — зеркало mp3_read_at_fn
 type ReadAtFn = unsafe extern "C" fn(
     user_ctx: *mut c_void,
@@ -78,7 +98,580 @@ pub struct Mp3HostApi {
 
 struct RustSession {
     /// Копия хост-API для обратных вызовов при анализе
-    _host_api: Mp3HostApi,
+    host_api: Mp3HostApi,
+}
+
+// =============================================================================
+// Разбор MPEG Audio
+// =============================================================================
+
+/// Таблицы битрейта (кбит/с) по (версия, слой), индексируются полем `E`.
+/// Индекс 0 — «free», индекс 15 — запрещён; помечаем их нулём.
+const BITRATE_V1_L1: [u32; 16] = [
+    0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0,
+];
+const BITRATE_V1_L2: [u32; 16] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0,
+];
+const BITRATE_V1_L3: [u32; 16] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0,
+];
+const BITRATE_V2_L1: [u32; 16] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0,
+];
+const BITRATE_V2_L23: [u32; 16] = [
+    0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+];
+
+/// Частоты дискретизации (Гц) по версии, индексируются полем `F`.
+const SAMPLE_RATE_V1: [u32; 4] = [44100, 48000, 32000, 0];
+const SAMPLE_RATE_V2: [u32; 4] = [22050, 24000, 16000, 0];
+const SAMPLE_RATE_V25: [u32; 4] = [11025, 12000, 8000, 0];
+
+/// Разобранный заголовок одного MPEG-кадра.
+#[derive(Clone, Copy)]
+struct FrameHeader {
+    /// Версия: 1 = MPEG1, 2 = MPEG2, 25 = MPEG2.5
+    version: u8,
+    sample_rate: u32,
+    channels: u16,
+    /// Битрейт кадра в бит/с
+    bitrate: u32,
+    /// Полная длина кадра в байтах (с учётом паддинга)
+    frame_len: u32,
+    /// Число сэмплов, кодируемых одним кадром
+    samples_per_frame: u32,
+}
+
+impl FrameHeader {
+    /// Попытаться разобрать 4-байтовый заголовок кадра.
+    /// Возвращает `None`, если синхрослово или поля недопустимы.
+    fn parse(b: &[u8]) -> Option<FrameHeader> {
+        if b.len() < 4 {
+            return None;
+        }
+        // 11-битное синхрослово 0xFFE
+        if b[0] != 0xFF || (b[1] & 0xE0) != 0xE0 {
+            return None;
+        }
+
+        let version = match (b[1] >> 3) & 0x3 {
+            0 => 25u8,
+            2 => 2,
+            3 => 1,
+            _ => return None, // 01 — зарезервировано
+        };
+        let layer = match (b[1] >> 1) & 0x3 {
+            1 => 3u8,
+            2 => 2,
+            3 => 1,
+            _ => return None, // 00 — зарезервировано
+        };
+
+        let bitrate_index = ((b[2] >> 4) & 0xF) as usize;
+        let sr_index = ((b[2] >> 2) & 0x3) as usize;
+        let padding = ((b[2] >> 1) & 0x1) as u32;
+        let channel_mode = (b[3] >> 6) & 0x3;
+
+        let bitrate_kbps = match (version, layer) {
+            (1, 1) => BITRATE_V1_L1,
+            (1, 2) => BITRATE_V1_L2,
+            (1, 3) => BITRATE_V1_L3,
+            (_, 1) => BITRATE_V2_L1,
+            (_, _) => BITRATE_V2_L23,
+        }[bitrate_index];
+        if bitrate_kbps == 0 {
+            return None;
+        }
+        let bitrate = bitrate_kbps * 1000;
+
+        let sample_rate = match version {
+            1 => SAMPLE_RATE_V1,
+            2 => SAMPLE_RATE_V2,
+            _ => SAMPLE_RATE_V25,
+        }[sr_index];
+        if sample_rate == 0 {
+            return None;
+        }
+
+        let channels: u16 = if channel_mode == 3 { 1 } else { 2 };
+
+        let samples_per_frame = match (version, layer) {
+            (_, 1) => 384,
+            (_, 2) => 1152,
+            (1, 3) => 1152,
+            (_, _) => 576,
+        };
+
+        let frame_len = match layer {
+            1 => (12 * bitrate / sample_rate + padding) * 4,
+            3 if version != 1 => 72 * bitrate / sample_rate + padding,
+            _ => 144 * bitrate / sample_rate + padding,
+        };
+        if frame_len < 4 {
+            return None;
+        }
+
+        Some(FrameHeader {
+            version,
+            sample_rate,
+            channels,
+            bitrate,
+            frame_len,
+            samples_per_frame,
+        })
+    }
+}
+
+/// Предпочтительный размер блока подкачки буфера (байт).
+const REFILL_BLOCK: usize = 8 * 1024;
+
+/// Буфер с подкачкой поверх `read_at`.
+///
+/// Держит одно окно данных и выдаёт произвольные срезы по абсолютному
+/// смещению, не загружая источник целиком. Позволяет сканеру работать с
+/// несколькими килобайтами памяти независимо от `source_size`.
+struct StreamBuffer<'a> {
+    api: &'a Mp3HostApi,
+    /// Абсолютное смещение первого байта окна.
+    win_start: u64,
+    buf: Vec<u8>,
+}
+
+impl<'a> StreamBuffer<'a> {
+    fn new(api: &'a Mp3HostApi) -> StreamBuffer<'a> {
+        StreamBuffer {
+            api,
+            win_start: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Перечитать окно начиная с `start`, запросив до `want` байт.
+    /// Короткие чтения (`out_read < requested`) повторяются до EOF
+    /// (нулевого чтения); ненулевой код callback пробрасывается как `Err`.
+    unsafe fn refill(&mut self, start: u64, want: usize) -> Result<(), i32> {
+        let read_at = self.api.read_at.ok_or(MP3_ERR_INVALID_ARG)?;
+        let remaining = self.api.source_size.saturating_sub(start) as usize;
+        let want = want.min(remaining);
+        self.buf.clear();
+        self.buf.resize(want, 0);
+        let mut got = 0usize;
+        while got < want {
+            let mut n = 0usize;
+            let rc = read_at(
+                self.api.user_ctx,
+                start + got as u64,
+                self.buf[got..].as_mut_ptr(),
+                want - got,
+                &mut n,
+            );
+            if rc != 0 {
+                return Err(MP3_ERR_IO);
+            }
+            if n == 0 {
+                break; // EOF раньше ожидаемого
+            }
+            got += n;
+        }
+        self.buf.truncate(got);
+        self.win_start = start;
+        Ok(())
+    }
+
+    /// Гарантировать доступность `dst.len()` байт по абсолютному смещению
+    /// `abs` и скопировать их в `dst`. Возвращает фактически скопированное
+    /// число байт (меньше запрошенного — у конца источника).
+    unsafe fn read_at_abs(&mut self, abs: u64, dst: &mut [u8]) -> Result<usize, i32> {
+        let covered =
+            abs >= self.win_start && abs + dst.len() as u64 <= self.win_start + self.buf.len() as u64;
+        if !covered {
+            self.refill(abs, dst.len().max(REFILL_BLOCK))?;
+        }
+        let rel = (abs - self.win_start) as usize;
+        let avail = self.buf.len().saturating_sub(rel);
+        let take = avail.min(dst.len());
+        dst[..take].copy_from_slice(&self.buf[rel..rel + take]);
+        Ok(take)
+    }
+}
+
+/// Границы MPEG-полезной нагрузки `[start, end)` после отбрасывания тегов.
+///
+/// В начале вырезается ID3v2, в конце — ID3v1 (`TAG`, 128 байт) и APE-тег
+/// (`APETAGEX`). Благодаря этому `data_size` и CBR-оценка считаются только по
+/// аудио-байтам, а мусорные «кадры» в тегах не декодируются.
+unsafe fn audio_bounds(buf: &mut StreamBuffer) -> Result<(u64, u64), i32> {
+    let size = buf.api.source_size;
+    let mut start = 0u64;
+    let mut end = size;
+
+    // --- ID3v2 в начале ---
+    let mut head = [0u8; 10];
+    if buf.read_at_abs(0, &mut head)? == 10 && &head[0..3] == b"ID3" {
+        let flags = head[5];
+        // Синхробезопасный размер: в каждом байте значимы только младшие 7 бит.
+        let sz = ((head[6] as u64) << 21)
+            | ((head[7] as u64) << 14)
+            | ((head[8] as u64) << 7)
+            | (head[9] as u64);
+        start = 10 + sz;
+        if flags & 0x10 != 0 {
+            start += 10; // присутствует футер
+        }
+        start = start.min(end);
+    }
+
+    // --- ID3v1 в конце ---
+    if end >= start + 128 {
+        let mut tag = [0u8; 3];
+        if buf.read_at_abs(end - 128, &mut tag)? == 3 && &tag == b"TAG" {
+            end -= 128;
+        }
+    }
+
+    // --- APE-тег в конце ---
+    if end >= start + 32 {
+        let mut footer = [0u8; 32];
+        if buf.read_at_abs(end - 32, &mut footer)? == 32 && &footer[0..8] == b"APETAGEX" {
+            // Размер тега (футер + элементы) — LE u32 по смещению 12.
+            let tag_size =
+                u32::from_le_bytes([footer[12], footer[13], footer[14], footer[15]]) as u64;
+            // Флаг наличия заголовка — старший бит дворда флагов (смещение 20).
+            let has_header = footer[23] & 0x80 != 0;
+            let total = tag_size + if has_header { 32 } else { 0 };
+            if end >= start + total {
+                end -= total;
+            }
+        }
+    }
+
+    Ok((start, end))
+}
+
+/// Найти первый достоверный кадр в диапазоне `[start, end)`.
+/// Достоверность подтверждается тем, что по вычисленной длине кадра
+/// следует ещё одно синхрослово — это отсекает ложные срабатывания.
+unsafe fn find_first_frame(
+    buf: &mut StreamBuffer,
+    start: u64,
+    end: u64,
+) -> Result<Option<(u64, FrameHeader)>, i32> {
+    let mut off = start;
+    while off + 4 <= end {
+        let mut hdr = [0u8; 4];
+        if buf.read_at_abs(off, &mut hdr)? < 4 {
+            break;
+        }
+        if let Some(fh) = FrameHeader::parse(&hdr) {
+            let next = off + fh.frame_len as u64;
+            // Следующий кадр либо в пределах данных и синхронизирован,
+            // либо кадр — последний в потоке.
+            if next + 4 > end {
+                return Ok(Some((off, fh)));
+            }
+            let mut nhdr = [0u8; 4];
+            if buf.read_at_abs(next, &mut nhdr)? == 4 && FrameHeader::parse(&nhdr).is_some() {
+                return Ok(Some((off, fh)));
+            }
+        }
+        off += 1;
+    }
+    Ok(None)
+}
+
+/// Размер блока side-information кадра — смещение тега Xing/Info от конца
+/// 4-байтового заголовка, зависящее от версии и числа каналов.
+fn side_info_size(first: &FrameHeader) -> usize {
+    match (first.version, first.channels) {
+        (1, 1) => 17,
+        (1, _) => 32,
+        (_, 1) => 9,
+        (_, _) => 17,
+    }
+}
+
+/// Данные LAME-расширения, следующего сразу за тегом Info/Xing.
+#[derive(Clone, Copy, Default)]
+struct LameInfo {
+    /// Задержка энкодера в сэмплах.
+    delay: u16,
+    /// Хвостовой паддинг энкодера в сэмплах.
+    padding: u16,
+    /// ReplayGain дорожки и альбома, единицы 0.1 дБ (знаковые, декодированы
+    /// из знак-величины).
+    rg_track: i16,
+    rg_album: i16,
+}
+
+/// Декодировать 16-битное значение ReplayGain из представления знак-величина.
+fn decode_replaygain(word: u16) -> i16 {
+    let magnitude = (word & 0x7FFF) as i16;
+    if word & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Разобрать LAME-расширение, если тег Info/Xing присутствует.
+///
+/// Расширение следует сразу за тегом Xing/Info: магия(4) + флаги(4), затем
+/// только те из полей frames(4)/bytes(4)/TOC(100)/quality(4), чьи биты
+/// выставлены в флагах. Задержка/паддинг лежат в 24-битном поле по смещению
+/// `0x15` от начала расширения (после 9-байтовой строки версии энкодера и
+/// служебных байт): старшие 12 бит — задержка, младшие — паддинг. Сразу за
+/// ним идут два слова ReplayGain (дорожка, альбом).
+unsafe fn parse_lame(
+    buf: &mut StreamBuffer,
+    first_off: u64,
+    first: &FrameHeader,
+) -> Result<Option<LameInfo>, i32> {
+    let xing_off = first_off + 4 + side_info_size(first) as u64;
+    let mut head = [0u8; 8];
+    if buf.read_at_abs(xing_off, &mut head)? < 8
+        || (&head[0..4] != b"Xing" && &head[0..4] != b"Info")
+    {
+        return Ok(None);
+    }
+    let flags = u32::from_be_bytes([head[4], head[5], head[6], head[7]]);
+    let mut optional_size = 0u64;
+    if flags & 0x1 != 0 {
+        optional_size += 4; // frames
+    }
+    if flags & 0x2 != 0 {
+        optional_size += 4; // bytes
+    }
+    if flags & 0x4 != 0 {
+        optional_size += 100; // TOC
+    }
+    if flags & 0x8 != 0 {
+        optional_size += 4; // quality
+    }
+    let lame_ext_off = xing_off + 8 + optional_size;
+
+    // 3 байта задержки/паддинга по 0x15 и два слова ReplayGain сразу за ними.
+    let mut ext = [0u8; 7];
+    if buf.read_at_abs(lame_ext_off + 0x15, &mut ext)? < 7 {
+        return Ok(None);
+    }
+    let field = ((ext[0] as u32) << 16) | ((ext[1] as u32) << 8) | (ext[2] as u32);
+    let delay = (field >> 12) as u16;
+    let padding = (field & 0xFFF) as u16;
+    let rg_track = decode_replaygain(u16::from_be_bytes([ext[3], ext[4]]));
+    let rg_album = decode_replaygain(u16::from_be_bytes([ext[5], ext[6]]));
+
+    Ok(Some(LameInfo {
+        delay,
+        padding,
+        rg_track,
+        rg_album,
+    }))
+}
+
+/// Число MPEG-кадров по заголовку Xing/Info или VBRI, если он присутствует.
+///
+/// Xing/Info лежит сразу за блоком side-information первого кадра; смещение
+/// блока зависит от версии и числа каналов. VBRI всегда стоит на фиксированном
+/// смещении `0x24` от начала первого кадра.
+unsafe fn vbr_frame_count(
+    buf: &mut StreamBuffer,
+    first_off: u64,
+    first: &FrameHeader,
+) -> Result<Option<u64>, i32> {
+    // --- Xing / Info --- магия(4) + флаги(4) + число кадров(4)
+    let xing_off = first_off + 4 + side_info_size(first) as u64;
+    let mut x = [0u8; 12];
+    if buf.read_at_abs(xing_off, &mut x)? == 12 && (&x[0..4] == b"Xing" || &x[0..4] == b"Info") {
+        let flags = u32::from_be_bytes([x[4], x[5], x[6], x[7]]);
+        if flags & 0x1 != 0 {
+            return Ok(Some(u32::from_be_bytes([x[8], x[9], x[10], x[11]]) as u64));
+        }
+    }
+
+    // --- VBRI --- число кадров лежит big-endian по смещению 0x0E
+    let vbri_off = first_off + 0x24;
+    let mut v = [0u8; 0x12];
+    if buf.read_at_abs(vbri_off, &mut v)? == 0x12 && &v[0..4] == b"VBRI" {
+        return Ok(Some(
+            u32::from_be_bytes([v[0x0E], v[0x0F], v[0x10], v[0x11]]) as u64
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Полный потоковый разбор MPEG-потока в `Mp3AudioInfo`.
+/// Диапазон `[audio_start, audio_end)` — байты без учёта тегов-метаданных.
+unsafe fn parse_mpeg(
+    buf: &mut StreamBuffer,
+    audio_start: u64,
+    audio_end: u64,
+) -> Result<Option<Mp3AudioInfo>, i32> {
+    let (first_off, first) = match find_first_frame(buf, audio_start, audio_end)? {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+
+    // Пройти цепочку кадров: посчитать байты, кадры и собрать признак VBR.
+    let mut off = first_off;
+    let mut frames: u64 = 0;
+    let mut total_bytes: u64 = 0;
+    let mut vbr = false;
+    let mut vbr_duration_ms: u64 = 0;
+
+    while off + 4 <= audio_end {
+        let mut hdr = [0u8; 4];
+        if buf.read_at_abs(off, &mut hdr)? < 4 {
+            break;
+        }
+        let fh = match FrameHeader::parse(&hdr) {
+            Some(fh) => fh,
+            None => break,
+        };
+        if fh.bitrate != first.bitrate {
+            vbr = true;
+        }
+        vbr_duration_ms += fh.samples_per_frame as u64 * 1000 / fh.sample_rate as u64;
+        frames += 1;
+        total_bytes += fh.frame_len as u64;
+        off += fh.frame_len as u64;
+    }
+
+    if frames == 0 {
+        return Ok(None);
+    }
+
+    let data_size = total_bytes;
+
+    let lame = parse_lame(buf, first_off, &first)?.unwrap_or_default();
+
+    // Приоритет — точному счётчику кадров из Xing/Info/VBRI.
+    let (duration_ms, method) = if let Some(count) = vbr_frame_count(buf, first_off, &first)? {
+        // Вычесть задержку и паддинг энкодера для сэмпл-точной длины.
+        let total_samples = (count * first.samples_per_frame as u64)
+            .saturating_sub(lame.delay as u64 + lame.padding as u64);
+        let ms = total_samples * 1000 / first.sample_rate as u64;
+        (ms, MP3_DURATION_MEASURED)
+    } else if vbr {
+        // VBR без заголовка — суммируем длительности пройденных кадров.
+        (vbr_duration_ms, MP3_DURATION_MEASURED)
+    } else {
+        // CBR: оценка из размера и постоянного битрейта.
+        (data_size * 8 * 1000 / first.bitrate as u64, MP3_DURATION_ESTIMATED)
+    };
+
+    Ok(Some(Mp3AudioInfo {
+        sample_rate: first.sample_rate,
+        channels: first.channels,
+        bits_per_sample: 0,
+        bitrate: first.bitrate,
+        duration_ms: duration_ms as u32,
+        data_size,
+        valid: 1,
+        duration_method: method,
+        encoder_delay: lame.delay,
+        encoder_padding: lame.padding,
+        replaygain_track: lame.rg_track,
+        replaygain_album: lame.rg_album,
+    }))
+}
+
+// =============================================================================
+// Разбор RIFF/WAVE
+// =============================================================================
+
+/// Прочитать little-endian u16 по смещению `off`.
+fn read_le_u16(data: &[u8], off: usize) -> Option<u16> {
+    data.get(off..off + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+/// Прочитать little-endian u32 по смещению `off`.
+fn read_le_u32(data: &[u8], off: usize) -> Option<u32> {
+    data.get(off..off + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Разобрать WAVE-контейнер: формат из чанка `fmt ` и длину чанка `data`.
+unsafe fn parse_wave(buf: &mut StreamBuffer) -> Result<Option<Mp3AudioInfo>, i32> {
+    let size = buf.api.source_size;
+    let mut riff = [0u8; 12];
+    if buf.read_at_abs(0, &mut riff)? < 12 || &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Ok(None);
+    }
+
+    let mut off = 12u64;
+    let mut format_tag = 0u16;
+    let mut channels = 0u16;
+    let mut sample_rate = 0u32;
+    let mut bits_per_sample = 0u16;
+    let mut data_size = 0u64;
+    let mut have_fmt = false;
+    let mut have_data = false;
+
+    // Обход списка чанков: 4 байта идентификатора, LE u32 размера, тело.
+    while off + 8 <= size {
+        let mut ch = [0u8; 8];
+        if buf.read_at_abs(off, &mut ch)? < 8 {
+            break;
+        }
+        let chunk_size = read_le_u32(&ch, 4).unwrap_or(0) as u64;
+        let body = off + 8;
+        if &ch[0..4] == b"fmt " {
+            let mut fmt = [0u8; 40];
+            let got = buf.read_at_abs(body, &mut fmt[..chunk_size.min(40) as usize])?;
+            if got >= 16 {
+                format_tag = read_le_u16(&fmt, 0).unwrap_or(0);
+                channels = read_le_u16(&fmt, 2).unwrap_or(0);
+                sample_rate = read_le_u32(&fmt, 4).unwrap_or(0);
+                bits_per_sample = read_le_u16(&fmt, 14).unwrap_or(0);
+                // WAVE_FORMAT_EXTENSIBLE хранит истинный код формата в первых
+                // двух байтах SubFormat GUID, идущего сразу за cbSize/valid bits/маской каналов.
+                if format_tag == 0xFFFE && got >= 40 {
+                    format_tag = read_le_u16(&fmt, 24).unwrap_or(0);
+                }
+                have_fmt = true;
+            }
+        } else if &ch[0..4] == b"data" {
+            data_size = chunk_size;
+            have_data = true;
+        }
+        // Чанки выровнены по чётной границе.
+        off = body + chunk_size + (chunk_size & 1);
+    }
+
+    // Нулевые частота/число каналов — как и в MPEG-парсере, признак
+    // повреждённого заголовка, а не валидного потока.
+    if !have_fmt || !have_data || sample_rate == 0 || channels == 0 {
+        return Ok(None);
+    }
+
+    // Формула длительности ниже верна только для целочисленного PCM — для
+    // прочих кодеков (ADPCM и т.п.) bits_per_sample не равен байтам на сэмпл,
+    // так что такие потоки не распознаём, а не гадаем на кривой формуле.
+    const WAVE_FORMAT_PCM: u16 = 1;
+    if format_tag != WAVE_FORMAT_PCM {
+        return Ok(None);
+    }
+
+    // Длительность PCM: байты / (частота * каналы * байт на сэмпл).
+    let bytes_per_sec = sample_rate as u64 * channels as u64 * (bits_per_sample as u64 / 8);
+    let duration_ms = (data_size * 1000).checked_div(bytes_per_sec).unwrap_or(0);
+
+    Ok(Some(Mp3AudioInfo {
+        sample_rate,
+        channels,
+        bits_per_sample,
+        bitrate: (bytes_per_sec * 8) as u32,
+        duration_ms: duration_ms as u32,
+        data_size,
+        valid: 1,
+        duration_method: MP3_DURATION_MEASURED,
+        encoder_delay: 0,
+        encoder_padding: 0,
+        replaygain_track: 0,
+        replaygain_album: 0,
+    }))
 }
 
 // =============================================================================
@@ -102,7 +695,7 @@ pub unsafe extern "C" fn mp3_rust_session_init_impl(
     let api_copy = core::ptr::read(host_api);
 
     let session = Box::new(RustSession {
-        _host_api: api_copy,
+        host_api: api_copy,
     });
 
     *out_rust_session = Box::into_raw(session) as *mut c_void;
@@ -111,7 +704,9 @@ pub unsafe extern "C" fn mp3_rust_session_init_impl(
 
 /// Выполнить разбор MP3
 ///
-/// **STUB**: всегда возвращает фиксированные значения.
+/// Читает источник через `read_at`, сканирует MPEG-кадры и заполняет
+/// `out_info`. При отсутствии достоверной цепочки кадров поле `valid`
+/// выставляется в `0`.
 ///
 /// # Safety
 /// Вызывается из C/C++. Указатели должны быть валидны.
@@ -124,26 +719,50 @@ pub unsafe extern "C" fn mp3_rust_session_run_impl(
         return MP3_ERR_INVALID_PTR;
     }
 
-    let _session = &*(rust_session as *const RustSession);
+    let session = &*(rust_session as *const RustSession);
+    let mut buf = StreamBuffer::new(&session.host_api);
 
-    // =========================================================================
-    // TODO: Реальная реализация:
-    //   1. Прочитать данные через _session.host_api.read_at
-    //   2. Распарсить MP3-фреймы / Xing / VBRI заголовки
-    //   3. Заполнить out_info реальными значениями
-    // =========================================================================
+    // Диспетчеризация по сигнатуре: WAVE-контейнер либо MPEG-поток.
+    let mut sig = [0u8; 12];
+    let got = match buf.read_at_abs(0, &mut sig) {
+        Ok(got) => got,
+        Err(rc) => return rc,
+    };
+    let is_wave = got >= 12 && &sig[0..4] == b"RIFF" && &sig[8..12] == b"WAVE";
 
-    *out_info = Mp3AudioInfo {
-        sample_rate: 44100,
-        channels: 2,
-        bits_per_sample: 16,
-        bitrate: 128_000,
-        duration_ms: 1000, // stub: всегда 1 секунда
-        data_size: 0,
-        valid: 1,
+    let parsed = if is_wave {
+        parse_wave(&mut buf)
+    } else {
+        match audio_bounds(&mut buf) {
+            Ok((start, end)) => parse_mpeg(&mut buf, start, end),
+            Err(rc) => Err(rc),
+        }
     };
 
-    MP3_OK
+    match parsed {
+        Ok(Some(info)) => {
+            *out_info = info;
+            MP3_OK
+        }
+        Ok(None) => {
+            *out_info = Mp3AudioInfo {
+                sample_rate: 0,
+                channels: 0,
+                bits_per_sample: 0,
+                bitrate: 0,
+                duration_ms: 0,
+                data_size: 0,
+                valid: 0,
+                duration_method: MP3_DURATION_ESTIMATED,
+                encoder_delay: 0,
+                encoder_padding: 0,
+                replaygain_track: 0,
+                replaygain_album: 0,
+            };
+            MP3_ERR_INVALID_FORMAT
+        }
+        Err(rc) => rc,
+    }
 }
 
 /// Завершить сессию и освободить память
@@ -156,3 +775,556 @@ pub unsafe extern "C" fn mp3_rust_session_deinit_impl(rust_session: *mut c_void)
         let _ = Box::from_raw(rust_session as *mut RustSession);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `read_at`, читающий из буфера, на который указывает `user_ctx`.
+    unsafe extern "C" fn read_from_slice(
+        user_ctx: *mut c_void,
+        offset: u64,
+        dst: *mut u8,
+        requested: usize,
+        out_read: *mut usize,
+    ) -> i32 {
+        let data = &*(user_ctx as *const Vec<u8>);
+        let offset = offset as usize;
+        if offset >= data.len() {
+            *out_read = 0;
+            return MP3_OK;
+        }
+        let n = requested.min(data.len() - offset);
+        core::ptr::copy_nonoverlapping(data[offset..].as_ptr(), dst, n);
+        *out_read = n;
+        MP3_OK
+    }
+
+    /// Собрать `Mp3HostApi`, отдающий байты `data` через `read_at`.
+    fn host_api(data: &Vec<u8>) -> Mp3HostApi {
+        Mp3HostApi {
+            user_ctx: data as *const Vec<u8> as *mut c_void,
+            source_size: data.len() as u64,
+            read_at: Some(read_from_slice),
+            alloc: None,
+            free: None,
+            log: None,
+        }
+    }
+
+    /// `read_at`, отдающий не более 3 байт за вызов — имитирует короткие чтения
+    /// источника, вынуждая `StreamBuffer::refill` повторять запрос до EOF.
+    unsafe extern "C" fn read_short_chunks(
+        user_ctx: *mut c_void,
+        offset: u64,
+        dst: *mut u8,
+        requested: usize,
+        out_read: *mut usize,
+    ) -> i32 {
+        let data = &*(user_ctx as *const Vec<u8>);
+        let offset = offset as usize;
+        if offset >= data.len() {
+            *out_read = 0;
+            return MP3_OK;
+        }
+        let n = requested.min(data.len() - offset).min(3);
+        core::ptr::copy_nonoverlapping(data[offset..].as_ptr(), dst, n);
+        *out_read = n;
+        MP3_OK
+    }
+
+    /// `read_at`, всегда завершающийся ошибкой хоста (ненулевой код).
+    unsafe extern "C" fn read_always_fails(
+        _user_ctx: *mut c_void,
+        _offset: u64,
+        _dst: *mut u8,
+        _requested: usize,
+        _out_read: *mut usize,
+    ) -> i32 {
+        7
+    }
+
+    #[test]
+    fn stream_buffer_retries_short_reads_until_full_or_eof() {
+        let data: Vec<u8> = (0u32..50).map(|i| i as u8).collect();
+        let api = Mp3HostApi {
+            user_ctx: &data as *const Vec<u8> as *mut c_void,
+            source_size: data.len() as u64,
+            read_at: Some(read_short_chunks),
+            alloc: None,
+            free: None,
+            log: None,
+        };
+        let mut buf = StreamBuffer::new(&api);
+        let mut dst = [0u8; 50];
+        unsafe {
+            // Запрошено ровно столько, сколько есть: `refill` должно собрать
+            // все 50 байт из чтений по 3 байта за раз.
+            let got = buf.read_at_abs(0, &mut dst).unwrap();
+            assert_eq!(got, 50);
+            assert_eq!(&dst[..], &data[..]);
+
+            // Запрос за пределами источника: короткое чтение на границе EOF.
+            let mut tail = [0u8; 10];
+            let got = buf.read_at_abs(45, &mut tail).unwrap();
+            assert_eq!(got, 5);
+            assert_eq!(&tail[..5], &data[45..50]);
+        }
+    }
+
+    #[test]
+    fn stream_buffer_read_straddling_window_boundary_refills_correctly() {
+        // Данные больше одного окна подкачки (`REFILL_BLOCK` = 8 КиБ).
+        let data: Vec<u8> = (0u32..20_000).map(|i| (i % 256) as u8).collect();
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            // Первое чтение заполняет окно [0, REFILL_BLOCK).
+            let mut head = [0u8; 8000];
+            assert_eq!(buf.read_at_abs(0, &mut head).unwrap(), 8000);
+            assert_eq!(&head[..], &data[0..8000]);
+
+            // Второе чтение пересекает правую границу того же окна —
+            // должно вызвать перечитывание окна, а не вернуть мусор/обрезку.
+            let mut straddle = [0u8; 10];
+            let got = buf.read_at_abs(8190, &mut straddle).unwrap();
+            assert_eq!(got, 10);
+            assert_eq!(&straddle[..], &data[8190..8200]);
+        }
+    }
+
+    #[test]
+    fn stream_buffer_maps_nonzero_read_at_rc_to_io_error() {
+        let data = vec![0u8; 16];
+        let api = Mp3HostApi {
+            user_ctx: &data as *const Vec<u8> as *mut c_void,
+            source_size: data.len() as u64,
+            read_at: Some(read_always_fails),
+            alloc: None,
+            free: None,
+            log: None,
+        };
+        let mut buf = StreamBuffer::new(&api);
+        let mut dst = [0u8; 4];
+        unsafe {
+            assert_eq!(buf.read_at_abs(0, &mut dst), Err(MP3_ERR_IO));
+        }
+    }
+
+    /// Синхробезопасная запись 28-битного размера ID3v2 (4 байта по 7 бит).
+    fn synchsafe_size(size: u32) -> [u8; 4] {
+        [
+            ((size >> 21) & 0x7F) as u8,
+            ((size >> 14) & 0x7F) as u8,
+            ((size >> 7) & 0x7F) as u8,
+            (size & 0x7F) as u8,
+        ]
+    }
+
+    #[test]
+    fn audio_bounds_skips_id3v2_header_without_footer() {
+        let mut data = vec![0xAAu8; 10 + 100 + 20];
+        data[0..3].copy_from_slice(b"ID3");
+        data[3] = 4; // версия
+        data[4] = 0; // ревизия
+        data[5] = 0x00; // флаги: футер отсутствует
+        data[6..10].copy_from_slice(&synchsafe_size(100));
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (start, end) = audio_bounds(&mut buf).unwrap();
+            assert_eq!(start, 10 + 100);
+            assert_eq!(end, data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn audio_bounds_skips_id3v2_header_with_footer_flag() {
+        let mut data = vec![0xAAu8; 10 + 100 + 10 + 20];
+        data[0..3].copy_from_slice(b"ID3");
+        data[3] = 4;
+        data[4] = 0;
+        data[5] = 0x10; // флаги: футер присутствует
+        data[6..10].copy_from_slice(&synchsafe_size(100));
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (start, end) = audio_bounds(&mut buf).unwrap();
+            assert_eq!(start, 10 + 100 + 10);
+            assert_eq!(end, data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn audio_bounds_cuts_trailing_id3v1_tag() {
+        let mut data = vec![0xAAu8; 50 + 128];
+        data[50..53].copy_from_slice(b"TAG");
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (start, end) = audio_bounds(&mut buf).unwrap();
+            assert_eq!(start, 0);
+            assert_eq!(end, 50);
+        }
+    }
+
+    /// Записать минимальный APE-футер (без тегов-элементов) в конец `data`.
+    fn write_ape_footer(data: &mut [u8], footer_off: usize, tag_size: u32, has_header: bool) {
+        data[footer_off..footer_off + 8].copy_from_slice(b"APETAGEX");
+        data[footer_off + 8..footer_off + 12].copy_from_slice(&2000u32.to_le_bytes());
+        data[footer_off + 12..footer_off + 16].copy_from_slice(&tag_size.to_le_bytes());
+        data[footer_off + 16..footer_off + 20].copy_from_slice(&0u32.to_le_bytes()); // item_count
+        let mut flags = [0u8; 4];
+        if has_header {
+            flags[3] = 0x80; // бит «заголовок присутствует» — старший бит LE-дворда
+        }
+        data[footer_off + 20..footer_off + 24].copy_from_slice(&flags);
+        // Байты 24..32 — зарезервированы, остаются нулевыми.
+    }
+
+    #[test]
+    fn audio_bounds_cuts_trailing_ape_tag_without_header() {
+        // Тег состоит из одного футера (32 байта), заголовка нет.
+        let mut data = vec![0xAAu8; 50 + 32];
+        write_ape_footer(&mut data, 50, 32, false);
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (start, end) = audio_bounds(&mut buf).unwrap();
+            assert_eq!(start, 0);
+            assert_eq!(end, 50);
+        }
+    }
+
+    #[test]
+    fn audio_bounds_cuts_trailing_ape_tag_with_header() {
+        // Заголовок (32 байта) + футер (32 байта, tag_size покрывает только сам футер).
+        let mut data = vec![0xAAu8; 50 + 32 + 32];
+        write_ape_footer(&mut data, 50 + 32, 32, true);
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (start, end) = audio_bounds(&mut buf).unwrap();
+            assert_eq!(start, 0);
+            assert_eq!(end, 50);
+        }
+    }
+
+    /// Собрать 4-байтовый заголовок MPEG1 Layer III, 128 кбит/с, 44100 Гц, стерео.
+    fn mpeg1_l3_header(padding: bool) -> [u8; 4] {
+        let pad_bit = if padding { 1u8 } else { 0u8 };
+        // sr_index = 0 (44100 Гц) опущен явно: `0 << 2` не меняет значение.
+        [0xFF, 0xFB, (0x9 << 4) | (pad_bit << 1), 0x00]
+    }
+
+    #[test]
+    fn parse_rejects_missing_sync() {
+        assert!(FrameHeader::parse(&[0x00, 0xFB, 0x90, 0x00]).is_none());
+    }
+
+    #[test]
+    fn parse_mpeg1_layer3_decodes_bitrate_and_sample_rate() {
+        let fh = FrameHeader::parse(&mpeg1_l3_header(false)).expect("valid frame header");
+        assert_eq!(fh.version, 1);
+        assert_eq!(fh.bitrate, 128_000);
+        assert_eq!(fh.sample_rate, 44_100);
+        assert_eq!(fh.channels, 2);
+        assert_eq!(fh.samples_per_frame, 1152);
+        // 144 * 128000 / 44100 = 417 (без паддинга)
+        assert_eq!(fh.frame_len, 417);
+    }
+
+    #[test]
+    fn parse_mpeg1_layer3_padding_adds_one_byte() {
+        let fh = FrameHeader::parse(&mpeg1_l3_header(true)).expect("valid frame header");
+        assert_eq!(fh.frame_len, 418);
+    }
+
+    #[test]
+    fn samples_per_frame_layer2_is_1152_for_every_mpeg_version() {
+        // MPEG1 Layer II, 160 кбит/с (индекс 9 в таблице V1_L2), 44100 Гц.
+        let v1 = FrameHeader::parse(&[0xFF, 0xFD, 0x90, 0x00]).expect("valid frame header");
+        assert_eq!(v1.samples_per_frame, 1152);
+
+        // MPEG2 Layer II, 64 кбит/с (индекс 8 в таблице V2_L23), 22050 Гц.
+        let v2 = FrameHeader::parse(&[0xFF, 0xF5, 0x80, 0x00]).expect("valid frame header");
+        assert_eq!(v2.version, 2);
+        assert_eq!(v2.samples_per_frame, 1152);
+
+        // MPEG2.5 Layer II — то же число сэмплов на кадр, что и MPEG2.
+        let v25 = FrameHeader::parse(&[0xFF, 0xE5, 0x80, 0x00]).expect("valid frame header");
+        assert_eq!(v25.version, 25);
+        assert_eq!(v25.samples_per_frame, 1152);
+    }
+
+    #[test]
+    fn samples_per_frame_layer3_halves_for_mpeg2() {
+        // MPEG2 Layer III, 64 кбит/с, 22050 Гц.
+        let fh = FrameHeader::parse(&[0xFF, 0xF3, 0x80, 0x00]).expect("valid frame header");
+        assert_eq!(fh.version, 2);
+        assert_eq!(fh.samples_per_frame, 576);
+    }
+
+    /// Собрать минимальный MPEG1 Layer III поток из `n` одинаковых кадров.
+    fn build_cbr_stream(n: usize) -> Vec<u8> {
+        let hdr = mpeg1_l3_header(false);
+        let frame_len = FrameHeader::parse(&hdr).unwrap().frame_len as usize;
+        let mut data = Vec::with_capacity(frame_len * n);
+        for _ in 0..n {
+            data.extend_from_slice(&hdr);
+            data.resize(data.len() + (frame_len - 4), 0);
+        }
+        data
+    }
+
+    #[test]
+    fn find_first_frame_locates_sync_and_confirms_next_frame() {
+        let data = build_cbr_stream(3);
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let (off, fh) = find_first_frame(&mut buf, 0, data.len() as u64)
+                .unwrap()
+                .expect("a frame chain");
+            assert_eq!(off, 0);
+            assert_eq!(fh.bitrate, 128_000);
+        }
+    }
+
+    /// Данные тега Xing и следующего за ним LAME-расширения для
+    /// `write_xing_and_lame`. `flags` управляет тем, какие опциональные поля
+    /// Xing-заголовка (frames/bytes/TOC/quality) присутствуют перед LAME-данными.
+    struct XingLame {
+        flags: u32,
+        frame_count: u32,
+        delay: u16,
+        padding: u16,
+        rg_track: i16,
+        rg_album: i16,
+    }
+
+    /// Вставить тег Xing и следующее за ним LAME-расширение по смещению
+    /// `xing_off` внутри `data`.
+    fn write_xing_and_lame(data: &mut [u8], xing_off: usize, fields: XingLame) {
+        data[xing_off..xing_off + 4].copy_from_slice(b"Xing");
+        data[xing_off + 4..xing_off + 8].copy_from_slice(&fields.flags.to_be_bytes());
+        let mut off = xing_off + 8;
+        if fields.flags & 0x1 != 0 {
+            data[off..off + 4].copy_from_slice(&fields.frame_count.to_be_bytes());
+            off += 4;
+        }
+        if fields.flags & 0x2 != 0 {
+            off += 4; // bytes — не используется в тесте
+        }
+        if fields.flags & 0x4 != 0 {
+            off += 100; // TOC — не используется в тесте
+        }
+        if fields.flags & 0x8 != 0 {
+            off += 4; // quality — не используется в тесте
+        }
+        // LAME-расширение: 0x15 байт служебных полей, затем 24-битное
+        // поле задержки/паддинга и два слова ReplayGain.
+        let lame_ext_off = off;
+        let field = ((fields.delay as u32) << 12) | (fields.padding as u32 & 0xFFF);
+        let field_bytes = field.to_be_bytes();
+        data[lame_ext_off + 0x15..lame_ext_off + 0x18].copy_from_slice(&field_bytes[1..4]);
+        let encode_rg = |g: i16| -> u16 {
+            if g < 0 {
+                0x8000 | (-g) as u16
+            } else {
+                g as u16
+            }
+        };
+        data[lame_ext_off + 0x18..lame_ext_off + 0x1A]
+            .copy_from_slice(&encode_rg(fields.rg_track).to_be_bytes());
+        data[lame_ext_off + 0x1A..lame_ext_off + 0x1C]
+            .copy_from_slice(&encode_rg(fields.rg_album).to_be_bytes());
+    }
+
+    #[test]
+    fn parse_lame_reads_delay_padding_and_replaygain_relative_to_xing_tag() {
+        let hdr = mpeg1_l3_header(false);
+        let fh = FrameHeader::parse(&hdr).unwrap();
+        // Стерео MPEG1 -> side_info_size = 32 байта.
+        let xing_off = 4 + side_info_size(&fh);
+        // Запас для LAME-расширения (служебные поля + 7 байт данных).
+        let mut data = vec![0u8; xing_off + 8 + 0x15 + 7 + 16];
+        data[0..4].copy_from_slice(&hdr);
+        write_xing_and_lame(
+            &mut data,
+            xing_off,
+            XingLame {
+                flags: 0x1,
+                frame_count: 1000,
+                delay: 576,
+                padding: 1057,
+                rg_track: 30,
+                rg_album: -20,
+            },
+        );
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let lame = parse_lame(&mut buf, 0, &fh).unwrap().expect("LAME tag");
+            assert_eq!(lame.delay, 576);
+            assert_eq!(lame.padding, 1057);
+            assert_eq!(lame.rg_track, 30);
+            assert_eq!(lame.rg_album, -20);
+        }
+    }
+
+    #[test]
+    fn vbr_frame_count_reads_xing_frame_field() {
+        let hdr = mpeg1_l3_header(false);
+        let fh = FrameHeader::parse(&hdr).unwrap();
+        let xing_off = 4 + side_info_size(&fh);
+        let mut data = vec![0u8; xing_off + 12];
+        data[0..4].copy_from_slice(&hdr);
+        data[xing_off..xing_off + 4].copy_from_slice(b"Xing");
+        data[xing_off + 4..xing_off + 8].copy_from_slice(&1u32.to_be_bytes());
+        data[xing_off + 8..xing_off + 12].copy_from_slice(&1234u32.to_be_bytes());
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let count = vbr_frame_count(&mut buf, 0, &fh).unwrap();
+            assert_eq!(count, Some(1234));
+        }
+    }
+
+    #[test]
+    fn vbr_frame_count_reads_vbri_header() {
+        let hdr = mpeg1_l3_header(false);
+        let fh = FrameHeader::parse(&hdr).unwrap();
+        let vbri_off = 0x24usize;
+        let mut data = vec![0u8; vbri_off + 0x12];
+        data[0..4].copy_from_slice(&hdr);
+        data[vbri_off..vbri_off + 4].copy_from_slice(b"VBRI");
+        data[vbri_off + 0x0E..vbri_off + 0x12].copy_from_slice(&5678u32.to_be_bytes());
+
+        let api = host_api(&data);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let count = vbr_frame_count(&mut buf, 0, &fh).unwrap();
+            assert_eq!(count, Some(5678));
+        }
+    }
+
+    /// Собрать минимальный WAVE-файл с чанками `fmt ` (тело `fmt_body`) и
+    /// `data` (байты `data_bytes`).
+    fn build_wave(fmt_body: &[u8], data_bytes: &[u8]) -> Vec<u8> {
+        let mut chunks = Vec::new();
+        chunks.extend_from_slice(b"fmt ");
+        chunks.extend_from_slice(&(fmt_body.len() as u32).to_le_bytes());
+        chunks.extend_from_slice(fmt_body);
+        if fmt_body.len() & 1 != 0 {
+            chunks.push(0);
+        }
+        chunks.extend_from_slice(b"data");
+        chunks.extend_from_slice(&(data_bytes.len() as u32).to_le_bytes());
+        chunks.extend_from_slice(data_bytes);
+        if data_bytes.len() & 1 != 0 {
+            chunks.push(0);
+        }
+
+        let mut wave = Vec::new();
+        wave.extend_from_slice(b"RIFF");
+        wave.extend_from_slice(&(4 + chunks.len() as u32).to_le_bytes());
+        wave.extend_from_slice(b"WAVE");
+        wave.extend_from_slice(&chunks);
+        wave
+    }
+
+    /// Тело стандартного PCM `fmt `-чанка (16 байт).
+    fn pcm_fmt_body(channels: u16, sample_rate: u32, bits_per_sample: u16) -> [u8; 16] {
+        let mut body = [0u8; 16];
+        body[0..2].copy_from_slice(&1u16.to_le_bytes()); // wFormatTag = WAVE_FORMAT_PCM
+        body[2..4].copy_from_slice(&channels.to_le_bytes());
+        body[4..8].copy_from_slice(&sample_rate.to_le_bytes());
+        let block_align = channels * (bits_per_sample / 8);
+        let avg_bytes_per_sec = sample_rate * block_align as u32;
+        body[8..12].copy_from_slice(&avg_bytes_per_sec.to_le_bytes());
+        body[12..14].copy_from_slice(&block_align.to_le_bytes());
+        body[14..16].copy_from_slice(&bits_per_sample.to_le_bytes());
+        body
+    }
+
+    /// Тело `fmt `-чанка WAVE_FORMAT_EXTENSIBLE (40 байт) с заданным кодом
+    /// формата в первых двух байтах SubFormat GUID.
+    fn extensible_fmt_body(
+        channels: u16,
+        sample_rate: u32,
+        bits_per_sample: u16,
+        sub_format: u16,
+    ) -> [u8; 40] {
+        let mut body = [0u8; 40];
+        body[0..2].copy_from_slice(&0xFFFEu16.to_le_bytes()); // WAVE_FORMAT_EXTENSIBLE
+        body[2..4].copy_from_slice(&channels.to_le_bytes());
+        body[4..8].copy_from_slice(&sample_rate.to_le_bytes());
+        let block_align = channels * (bits_per_sample / 8);
+        let avg_bytes_per_sec = sample_rate * block_align as u32;
+        body[8..12].copy_from_slice(&avg_bytes_per_sec.to_le_bytes());
+        body[12..14].copy_from_slice(&block_align.to_le_bytes());
+        body[14..16].copy_from_slice(&bits_per_sample.to_le_bytes());
+        body[16..18].copy_from_slice(&22u16.to_le_bytes()); // cbSize
+        body[18..20].copy_from_slice(&bits_per_sample.to_le_bytes()); // wValidBitsPerSample
+        body[20..24].copy_from_slice(&0u32.to_le_bytes()); // dwChannelMask
+        body[24..26].copy_from_slice(&sub_format.to_le_bytes()); // SubFormat GUID, первые 2 байта
+        body
+    }
+
+    #[test]
+    fn parse_wave_reads_standard_pcm_fmt_and_data_chunks() {
+        let fmt_body = pcm_fmt_body(1, 8000, 16);
+        let data_bytes = vec![0u8; 16_000];
+        let wave = build_wave(&fmt_body, &data_bytes);
+
+        let api = host_api(&wave);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let info = parse_wave(&mut buf).unwrap().expect("valid WAVE");
+            assert_eq!(info.sample_rate, 8000);
+            assert_eq!(info.channels, 1);
+            assert_eq!(info.bits_per_sample, 16);
+            assert_eq!(info.data_size, 16_000);
+            assert_eq!(info.duration_ms, 1000);
+            assert_eq!(info.duration_method, MP3_DURATION_MEASURED);
+        }
+    }
+
+    #[test]
+    fn parse_wave_resolves_extensible_pcm_subformat() {
+        let fmt_body = extensible_fmt_body(2, 44_100, 16, 1 /* PCM */);
+        let data_bytes = vec![0u8; 176_400];
+        let wave = build_wave(&fmt_body, &data_bytes);
+
+        let api = host_api(&wave);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            let info = parse_wave(&mut buf).unwrap().expect("valid extensible WAVE");
+            assert_eq!(info.sample_rate, 44_100);
+            assert_eq!(info.channels, 2);
+            assert_eq!(info.duration_ms, 1000);
+        }
+    }
+
+    #[test]
+    fn parse_wave_rejects_extensible_non_pcm_subformat() {
+        // Код саб-формата 3 = IEEE float, а не PCM.
+        let fmt_body = extensible_fmt_body(2, 44_100, 32, 3);
+        let data_bytes = vec![0u8; 1000];
+        let wave = build_wave(&fmt_body, &data_bytes);
+
+        let api = host_api(&wave);
+        let mut buf = StreamBuffer::new(&api);
+        unsafe {
+            assert!(parse_wave(&mut buf).unwrap().is_none());
+        }
+    }
+}